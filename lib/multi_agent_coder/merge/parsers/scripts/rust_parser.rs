@@ -1,9 +1,117 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use syn::visit::{self, Visit};
 use syn::{File, Item, ItemFn, ItemStruct, ItemTrait, ItemImpl, Visibility};
 
+/// Output encoding for analysis results, selected with `--format`
+/// (defaults to `json`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Ron,
+    Toml,
+    Msgpack,
+}
+
+impl OutputFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(OutputFormat::Json),
+            "ron" => Some(OutputFormat::Ron),
+            "toml" => Some(OutputFormat::Toml),
+            "msgpack" => Some(OutputFormat::Msgpack),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ErrorPayload {
+    error: String,
+}
+
+/// Serializes `value` in the requested format and writes it to stdout
+/// (MessagePack is written as raw bytes; the others as a trailing-newline
+/// line of text).
+fn emit<T: Serialize>(value: &T, format: OutputFormat) -> Result<(), String> {
+    match format {
+        OutputFormat::Json => {
+            let encoded =
+                serde_json::to_string(value).map_err(|e| format!("JSON encoding failed: {}", e))?;
+            println!("{}", encoded);
+        }
+        OutputFormat::Ron => {
+            let encoded =
+                ron::to_string(value).map_err(|e| format!("RON encoding failed: {}", e))?;
+            println!("{}", encoded);
+        }
+        OutputFormat::Toml => {
+            let encoded =
+                toml::to_string(value).map_err(|e| format!("TOML encoding failed: {}", e))?;
+            println!("{}", encoded);
+        }
+        OutputFormat::Msgpack => {
+            // Structs are encoded as field-name-keyed maps rather than
+            // positional arrays: `to_vec` would otherwise shift field
+            // order whenever a `skip_serializing_if` field is omitted.
+            let mut encoded = Vec::new();
+            value
+                .serialize(&mut rmp_serde::Serializer::new(&mut encoded).with_struct_map())
+                .map_err(|e| format!("MessagePack encoding failed: {}", e))?;
+            std::io::stdout()
+                .write_all(&encoded)
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Emits `message` as an error, encoded in `format` when that format can
+/// represent a simple `{ "error": ... }`-shaped payload, falling back to
+/// the original JSON error convention for MessagePack.
+fn emit_error(message: &str, format: OutputFormat) {
+    let payload = ErrorPayload {
+        error: message.to_string(),
+    };
+    match format {
+        OutputFormat::Json => eprintln!(
+            "{}",
+            serde_json::to_string(&payload).unwrap_or_else(|_| format!(r#"{{"error": "{}"}}"#, message))
+        ),
+        OutputFormat::Ron => eprintln!(
+            "{}",
+            ron::to_string(&payload).unwrap_or_else(|_| format!(r#"{{"error": "{}"}}"#, message))
+        ),
+        OutputFormat::Toml => eprintln!(
+            "{}",
+            toml::to_string(&payload).unwrap_or_else(|_| format!(r#"{{"error": "{}"}}"#, message))
+        ),
+        OutputFormat::Msgpack => eprintln!(r#"{{"error": "{}"}}"#, message),
+    }
+}
+
+/// Pulls `--format <name>` out of the argument list (defaulting to
+/// `json`), exiting with an error if the name is unrecognized.
+fn extract_format(args: &mut Vec<String>) -> OutputFormat {
+    let Some(pos) = args.iter().position(|a| a == "--format") else {
+        return OutputFormat::Json;
+    };
+    args.remove(pos);
+    if pos >= args.len() {
+        eprintln!(r#"{{"error": "--format requires a value"}}"#);
+        std::process::exit(1);
+    }
+    let value = args.remove(pos);
+    OutputFormat::parse(&value).unwrap_or_else(|| {
+        eprintln!(r#"{{"error": "Unknown format '{}'"}}"#, value);
+        std::process::exit(1);
+    })
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct ParseResult {
     functions: Vec<FunctionInfo>,
@@ -12,8 +120,6 @@ struct ParseResult {
     impls: Vec<TypeInfo>,
     imports: Vec<String>,
     dependencies: Vec<DependencyInfo>,
-    side_effects: Vec<String>,
-    complexity: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -24,6 +130,254 @@ struct FunctionInfo {
     public: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     async_fn: Option<bool>,
+    cyclomatic: u32,
+    cognitive: u32,
+    effects: Vec<Effect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
+    attributes: Vec<String>,
+    generics: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    return_type: Option<String>,
+}
+
+/// A category of observable side effect a function's body can perform,
+/// classified from its AST rather than by matching substrings in a
+/// stringified token stream.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Effect {
+    Io,
+    FileSystem,
+    Network,
+    Unsafe,
+    Alloc,
+    Panic,
+    Mutation,
+}
+
+/// Path prefixes (post `extract_callee_path` normalization, e.g.
+/// `"std::fs::read"`) mapped to the effect they imply. Extend this table
+/// to teach the classifier about new IO/network/allocator APIs.
+const EFFECT_PATH_TABLE: &[(&str, Effect)] = &[
+    ("std::io", Effect::Io),
+    ("std::fs", Effect::FileSystem),
+    ("tokio::fs", Effect::FileSystem),
+    ("File::", Effect::FileSystem),
+    ("std::net", Effect::Network),
+    ("tokio::net", Effect::Network),
+    ("reqwest::", Effect::Network),
+    ("hyper::", Effect::Network),
+    ("std::alloc", Effect::Alloc),
+    ("Box::new", Effect::Alloc),
+    ("Vec::with_capacity", Effect::Alloc),
+];
+
+/// Looks up the effect implied by a normalized callee path against
+/// `EFFECT_PATH_TABLE`.
+fn classify_path_effect(callee_path: &str) -> Option<Effect> {
+    EFFECT_PATH_TABLE
+        .iter()
+        .find(|(prefix, _)| callee_path.starts_with(prefix))
+        .map(|(_, effect)| *effect)
+}
+
+/// Method names mapped to the effect they imply. Method calls (e.g.
+/// `f.read_to_string(&mut buf)`) resolve through the receiver's inferred
+/// type rather than a qualified path, so they're classified by name
+/// against this table instead of `EFFECT_PATH_TABLE`. Extend this table
+/// to teach the classifier about new IO/network method idioms.
+///
+/// This is a name-only heuristic with no receiver-type information: a
+/// type that happens to define its own `read`/`write`/`send`/`recv`/
+/// `connect`/`accept` method unrelated to IO or networking (a channel,
+/// a parser, a custom builder) will be misclassified the same way. It
+/// trades that false-positive risk for catching the dominant method-call
+/// IO/network idiom without full type inference.
+const EFFECT_METHOD_TABLE: &[(&str, Effect)] = &[
+    ("read", Effect::Io),
+    ("read_to_string", Effect::Io),
+    ("read_to_end", Effect::Io),
+    ("read_exact", Effect::Io),
+    ("read_line", Effect::Io),
+    ("write", Effect::Io),
+    ("write_all", Effect::Io),
+    ("write_fmt", Effect::Io),
+    ("flush", Effect::Io),
+    ("send", Effect::Network),
+    ("recv", Effect::Network),
+    ("send_to", Effect::Network),
+    ("recv_from", Effect::Network),
+    ("connect", Effect::Network),
+    ("accept", Effect::Network),
+];
+
+/// Looks up the effect implied by a bare method name against
+/// `EFFECT_METHOD_TABLE`.
+fn classify_method_effect(method_name: &str) -> Option<Effect> {
+    EFFECT_METHOD_TABLE
+        .iter()
+        .find(|(name, _)| *name == method_name)
+        .map(|(_, effect)| *effect)
+}
+
+/// Splits an item's attributes into its doc comment (concatenated,
+/// leading space trimmed from each `///`/`#[doc = "..."]` line) and
+/// every other attribute, rendered as raw token strings.
+fn extract_doc_and_attrs(attrs: &[syn::Attribute]) -> (Option<String>, Vec<String>) {
+    let mut doc_lines = Vec::new();
+    let mut attributes = Vec::new();
+
+    for attr in attrs {
+        if attr.path().is_ident("doc") {
+            if let syn::Meta::NameValue(name_value) = &attr.meta {
+                if let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) = &name_value.value
+                {
+                    doc_lines.push(s.value().trim_start().to_string());
+                }
+            }
+        } else {
+            attributes.push(quote::quote!(#attr).to_string());
+        }
+    }
+
+    let doc = if doc_lines.is_empty() {
+        None
+    } else {
+        Some(doc_lines.join("\n"))
+    };
+
+    (doc, attributes)
+}
+
+/// Renders each generic parameter and the where-clause (if any) of an
+/// item's `Generics` as raw token strings.
+fn extract_generics(generics: &syn::Generics) -> Vec<String> {
+    let mut parts: Vec<String> = generics
+        .params
+        .iter()
+        .map(|param| quote::quote!(#param).to_string())
+        .collect();
+
+    if let Some(where_clause) = &generics.where_clause {
+        parts.push(quote::quote!(#where_clause).to_string());
+    }
+
+    parts
+}
+
+/// Renders a function's return type, or `None` when the signature
+/// declares none (an explicit `-> ()` is rendered as `"()"`).
+fn extract_return_type(output: &syn::ReturnType) -> Option<String> {
+    match output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(_, ty) => Some(quote::quote!(#ty).to_string()),
+    }
+}
+
+/// Walks a single function body to classify the side-effecting
+/// operations it performs: IO, filesystem, network, unsafe code,
+/// allocation, panics, and mutation (an `=` assignment to a dereferenced
+/// place or to a bare path, a `static` and a local variable alike;
+/// assignments to fields and compound assignments such as `+=` are not
+/// counted).
+struct EffectVisitor {
+    effects: HashSet<Effect>,
+}
+
+impl EffectVisitor {
+    fn new() -> Self {
+        EffectVisitor {
+            effects: HashSet::new(),
+        }
+    }
+
+    fn sorted(self) -> Vec<Effect> {
+        let mut effects: Vec<Effect> = self.effects.into_iter().collect();
+        effects.sort();
+        effects
+    }
+}
+
+impl<'ast> Visit<'ast> for EffectVisitor {
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        match expr {
+            syn::Expr::Call(call) => {
+                let callee = extract_callee_path(&quote::quote!(#call).to_string());
+                if let Some(effect) = classify_path_effect(&callee) {
+                    self.effects.insert(effect);
+                }
+            }
+            syn::Expr::MethodCall(m) => {
+                let method = m.method.to_string();
+                if method == "unwrap" || method == "expect" {
+                    self.effects.insert(Effect::Panic);
+                } else if let Some(effect) = classify_method_effect(&method) {
+                    self.effects.insert(effect);
+                }
+            }
+            syn::Expr::Macro(m) => {
+                let macro_name = m
+                    .mac
+                    .path
+                    .segments
+                    .last()
+                    .map(|s| s.ident.to_string())
+                    .unwrap_or_default();
+                match macro_name.as_str() {
+                    "println" | "print" | "eprintln" | "eprint" | "write" | "writeln" => {
+                        self.effects.insert(Effect::Io);
+                    }
+                    "panic" | "unreachable" | "todo" | "unimplemented" | "assert"
+                    | "assert_eq" | "assert_ne" | "debug_assert" => {
+                        self.effects.insert(Effect::Panic);
+                    }
+                    _ => {}
+                }
+            }
+            syn::Expr::Unsafe(_) => {
+                self.effects.insert(Effect::Unsafe);
+            }
+            syn::Expr::Assign(a) => {
+                let mutates = matches!(
+                    &*a.left,
+                    syn::Expr::Unary(u) if matches!(u.op, syn::UnOp::Deref(_))
+                ) || matches!(&*a.left, syn::Expr::Path(_));
+                if mutates {
+                    self.effects.insert(Effect::Mutation);
+                }
+            }
+            _ => {}
+        }
+
+        visit::visit_expr(self, expr);
+    }
+
+    /// Classifies macro invocations in both expression and statement
+    /// position (`println!(...);` parses as a statement macro, not an
+    /// `Expr::Macro`).
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let macro_name = mac
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default();
+        match macro_name.as_str() {
+            "println" | "print" | "eprintln" | "eprint" | "write" | "writeln" => {
+                self.effects.insert(Effect::Io);
+            }
+            "panic" | "unreachable" | "todo" | "unimplemented" | "assert"
+            | "assert_eq" | "assert_ne" | "debug_assert" => {
+                self.effects.insert(Effect::Panic);
+            }
+            _ => {}
+        }
+
+        visit::visit_macro(self, mac);
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -35,6 +389,10 @@ struct TypeInfo {
     fields: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     methods: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc: Option<String>,
+    attributes: Vec<String>,
+    generics: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,10 +400,41 @@ struct DependencyInfo {
     function: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     module: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caller: Option<String>,
+}
+
+/// One resolved edge in the whole-crate call graph: `caller` invokes
+/// `callee`, which resolves to `module` when it could be matched against
+/// a known definition (`None` for external/unresolved calls).
+#[derive(Serialize, Deserialize, Debug)]
+struct CallGraphEdge {
+    caller: String,
+    callee: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    module: Option<String>,
+}
+
+/// A single file's analysis plus the module path it was resolved to,
+/// as produced by whole-crate mode.
+#[derive(Serialize, Deserialize, Debug)]
+struct FileResult {
+    path: String,
+    module: String,
+    result: ParseResult,
+}
+
+/// Top-level output of `--crate <dir>`: every file's `ParseResult` plus
+/// the resolved call graph linking them together.
+#[derive(Serialize, Deserialize, Debug)]
+struct CrateResult {
+    files: Vec<FileResult>,
+    call_graph: Vec<CallGraphEdge>,
 }
 
 struct RustVisitor {
     result: ParseResult,
+    current_fn: Option<String>,
 }
 
 impl RustVisitor {
@@ -58,9 +447,8 @@ impl RustVisitor {
                 impls: Vec::new(),
                 imports: Vec::new(),
                 dependencies: Vec::new(),
-                side_effects: Vec::new(),
-                complexity: 1,
             },
+            current_fn: None,
         }
     }
 
@@ -69,8 +457,109 @@ impl RustVisitor {
     }
 }
 
+/// Walks a single function body to compute its cyclomatic and cognitive
+/// complexity, independent of the crate-wide `RustVisitor`.
+struct ComplexityVisitor {
+    cyclomatic: u32,
+    cognitive: u32,
+    nesting: u32,
+}
+
+impl ComplexityVisitor {
+    fn new() -> Self {
+        ComplexityVisitor {
+            cyclomatic: 1,
+            cognitive: 0,
+            nesting: 0,
+        }
+    }
+
+    /// Counts a branching construct at the current nesting depth:
+    /// `cyclomatic_increment` for cyclomatic complexity (1 for most
+    /// constructs, one per arm beyond the first for a `match`), and
+    /// `1 + nesting` for cognitive complexity so deeper branches cost more.
+    fn record_branch(&mut self, cyclomatic_increment: u32) {
+        self.cyclomatic += cyclomatic_increment;
+        self.cognitive += 1 + self.nesting;
+    }
+
+    fn enter_nesting(&mut self) {
+        self.nesting += 1;
+    }
+
+    fn exit_nesting(&mut self) {
+        self.nesting -= 1;
+    }
+}
+
+impl<'ast> Visit<'ast> for ComplexityVisitor {
+    /// Nested item definitions (e.g. a `fn` declared inside this function's
+    /// body) have their own complexity; don't fold them into this one.
+    fn visit_item(&mut self, _item: &'ast Item) {}
+
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        match expr {
+            syn::Expr::If(e) => {
+                self.record_branch(1);
+                self.enter_nesting();
+                visit::visit_expr_if(self, e);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::Match(m) => {
+                self.record_branch(m.arms.len().saturating_sub(1) as u32);
+                self.enter_nesting();
+                visit::visit_expr_match(self, m);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::While(e) => {
+                self.record_branch(1);
+                self.enter_nesting();
+                visit::visit_expr_while(self, e);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::Loop(e) => {
+                self.record_branch(1);
+                self.enter_nesting();
+                visit::visit_expr_loop(self, e);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::ForLoop(e) => {
+                self.record_branch(1);
+                self.enter_nesting();
+                visit::visit_expr_for_loop(self, e);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::Closure(e) => {
+                self.enter_nesting();
+                visit::visit_expr_closure(self, e);
+                self.exit_nesting();
+                return;
+            }
+            syn::Expr::Binary(bin) => {
+                if matches!(bin.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+                    self.record_branch(1);
+                }
+            }
+            syn::Expr::Try(_) => {
+                self.cyclomatic += 1;
+            }
+            _ => {}
+        }
+
+        visit::visit_expr(self, expr);
+    }
+}
+
 impl<'ast> Visit<'ast> for RustVisitor {
     fn visit_item(&mut self, item: &'ast Item) {
+        // Dispatch manually instead of delegating to `visit::visit_item`,
+        // which would match on `item` itself and call these same
+        // `visit_item_*` methods a second time.
         match item {
             Item::Fn(func) => self.visit_item_fn(func),
             Item::Struct(s) => self.visit_item_struct(s),
@@ -83,8 +572,6 @@ impl<'ast> Visit<'ast> for RustVisitor {
             }
             _ => {}
         }
-
-        visit::visit_item(self, item);
     }
 
     fn visit_item_fn(&mut self, func: &'ast ItemFn) {
@@ -104,8 +591,19 @@ impl<'ast> Visit<'ast> for RustVisitor {
             })
             .collect();
 
+        let mut complexity = ComplexityVisitor::new();
+        complexity.visit_block(&func.block);
+
+        let mut effects_visitor = EffectVisitor::new();
+        effects_visitor.visit_block(&func.block);
+        if func.sig.unsafety.is_some() || func.sig.abi.is_some() {
+            effects_visitor.effects.insert(Effect::Unsafe);
+        }
+
+        let (doc, attributes) = extract_doc_and_attrs(&func.attrs);
+
         self.result.functions.push(FunctionInfo {
-            name,
+            name: name.clone(),
             arity,
             params,
             public,
@@ -114,10 +612,18 @@ impl<'ast> Visit<'ast> for RustVisitor {
             } else {
                 None
             },
+            cyclomatic: complexity.cyclomatic,
+            cognitive: complexity.cognitive,
+            effects: effects_visitor.sorted(),
+            doc,
+            attributes,
+            generics: extract_generics(&func.sig.generics),
+            return_type: extract_return_type(&func.sig.output),
         });
 
-        // Calculate complexity
-        self.visit_block(&func.block);
+        let previous_fn = self.current_fn.replace(name);
+        visit::visit_block(self, &func.block);
+        self.current_fn = previous_fn;
     }
 
     fn visit_item_struct(&mut self, s: &'ast ItemStruct) {
@@ -130,12 +636,17 @@ impl<'ast> Visit<'ast> for RustVisitor {
             .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
             .collect();
 
+        let (doc, attributes) = extract_doc_and_attrs(&s.attrs);
+
         self.result.structs.push(TypeInfo {
             name,
             kind: "struct".to_string(),
             public,
             fields: Some(fields),
             methods: None,
+            doc,
+            attributes,
+            generics: extract_generics(&s.generics),
         });
     }
 
@@ -152,12 +663,17 @@ impl<'ast> Visit<'ast> for RustVisitor {
             })
             .collect();
 
+        let (doc, attributes) = extract_doc_and_attrs(&t.attrs);
+
         self.result.traits.push(TypeInfo {
             name,
             kind: "trait".to_string(),
             public,
             fields: None,
             methods: Some(methods),
+            doc,
+            attributes,
+            generics: extract_generics(&t.generics),
         });
     }
 
@@ -179,85 +695,702 @@ impl<'ast> Visit<'ast> for RustVisitor {
             })
             .collect();
 
+        let (doc, attributes) = extract_doc_and_attrs(&i.attrs);
+
         self.result.impls.push(TypeInfo {
             name,
             kind: "impl".to_string(),
             public: false,
             fields: None,
             methods: Some(methods),
+            doc,
+            attributes,
+            generics: extract_generics(&i.generics),
         });
     }
 
     fn visit_expr(&mut self, expr: &'ast syn::Expr) {
-        match expr {
-            syn::Expr::If(_) => self.result.complexity += 1,
-            syn::Expr::Match(m) => {
-                self.result.complexity += m.arms.len() as u32;
-            }
-            syn::Expr::While(_) | syn::Expr::Loop(_) | syn::Expr::ForLoop(_) => {
-                self.result.complexity += 1
+        if let syn::Expr::Call(call) = expr {
+            // Extract function calls
+            let func_name = quote::quote!(#call).to_string();
+
+            self.result.dependencies.push(DependencyInfo {
+                function: func_name,
+                module: None,
+                caller: self.current_fn.clone(),
+            });
+        }
+
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// Recursively collects every `.rs` file under `dir`.
+fn collect_rust_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rust_files(&path, out)?;
+        } else if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Derives a `::`-qualified module path from a file's location under
+/// `src_root`, e.g. `src/foo/bar.rs` -> `foo::bar`, `src/foo/mod.rs` ->
+/// `foo`, and `src/main.rs`/`src/lib.rs` -> the crate root (`""`).
+fn module_path_for(file_path: &Path, src_root: &Path) -> String {
+    let relative = file_path.strip_prefix(src_root).unwrap_or(file_path);
+    let mut segments: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    if matches!(segments.last().map(String::as_str), Some("mod" | "main" | "lib")) {
+        segments.pop();
+    }
+
+    segments.join("::")
+}
+
+/// Strips the call arguments off a `quote!`-stringified call expression
+/// (e.g. `"std :: fs :: read (path)"`) and normalizes `::` spacing, leaving
+/// just the callee path (`"std::fs::read"`).
+fn extract_callee_path(call_text: &str) -> String {
+    call_text
+        .split_once('(')
+        .map(|(callee, _)| callee)
+        .unwrap_or(call_text)
+        .replace(" :: ", "::")
+        .trim()
+        .to_string()
+}
+
+/// Expands one `use` item's quoted text into the individual paths it
+/// imports (a grouped import like `use a::{b, c};` yields one path per
+/// member), normalizing `::` spacing and dropping `as` aliases.
+fn parse_import_paths(import_text: &str) -> Vec<String> {
+    let normalized = import_text.replace(" :: ", "::").replace(" , ", ",");
+    let body = normalized
+        .trim_end_matches(';')
+        .trim()
+        .trim_start_matches("pub")
+        .trim()
+        .trim_start_matches("use")
+        .trim()
+        .to_string();
+
+    if let (Some(open), Some(close)) = (body.find('{'), body.rfind('}')) {
+        let prefix = body[..open].trim_end_matches("::");
+        body[open + 1..close]
+            .split(',')
+            .map(str::trim)
+            .filter(|member| !member.is_empty())
+            .map(|member| {
+                let name = member.split(" as ").next().unwrap_or(member).trim();
+                format!("{}::{}", prefix, name)
+            })
+            .collect()
+    } else {
+        let name = body.split(" as ").next().unwrap_or(&body).trim();
+        vec![name.to_string()]
+    }
+}
+
+/// Qualifies `name` with `module`, treating the empty module (a
+/// crate-root `main.rs`/`lib.rs`) as having no prefix rather than
+/// inserting a spurious leading `::`. Used consistently wherever a
+/// qualified symbol key is built, so lookups and inserts never diverge.
+fn qualify_name(module: &str, name: &str) -> String {
+    if module.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", module, name)
+    }
+}
+
+/// Resolves a call's callee path to the module that defines it, trying
+/// (in order): an exact match against the crate-wide qualified symbol
+/// table, the calling file's own module, the calling file's imports, and
+/// finally a crate-wide definition when the bare name is unambiguous.
+/// Returns `None` rather than guessing when multiple same-named
+/// definitions could apply.
+fn resolve_callee_module(
+    callee: &str,
+    caller_module: &str,
+    import_paths: &[String],
+    qualified_symbols: &HashMap<String, String>,
+    name_modules: &HashMap<String, HashSet<String>>,
+) -> Option<String> {
+    if let Some(module) = qualified_symbols.get(callee) {
+        return Some(module.clone());
+    }
+
+    if let Some((first_segment, rest)) = callee.split_once("::") {
+        // The call is qualified by a path segment; see if that segment
+        // is itself an alias brought into scope by an import.
+        return import_paths
+            .iter()
+            .filter(|import_path| import_path.rsplit("::").next() == Some(first_segment))
+            .find_map(|import_path| {
+                qualified_symbols.get(&format!("{}::{}", import_path, rest))
+            })
+            .cloned();
+    }
+
+    // Unqualified call: prefer a definition in the caller's own module.
+    let local_key = qualify_name(caller_module, callee);
+    if let Some(module) = qualified_symbols.get(&local_key) {
+        return Some(module.clone());
+    }
+
+    // Next, a symbol this file imported by its bare name.
+    if let Some(module) = import_paths
+        .iter()
+        .filter(|import_path| import_path.rsplit("::").next() == Some(callee))
+        .find_map(|import_path| qualified_symbols.get(import_path))
+    {
+        return Some(module.clone());
+    }
+
+    // Finally, fall back to a crate-wide definition only if the bare
+    // name is unambiguous; same-named definitions in multiple modules
+    // are left unresolved rather than picking an arbitrary winner.
+    match name_modules.get(callee).map(|modules| modules.len()) {
+        Some(1) => name_modules[callee].iter().next().cloned(),
+        _ => None,
+    }
+}
+
+const DEFAULT_CACHE_DIR: &str = ".rust_parser_cache";
+
+/// Where (and whether) to cache `ParseResult`s between invocations.
+struct CacheConfig {
+    dir: PathBuf,
+    enabled: bool,
+}
+
+/// Pulls `--cache-dir <dir>` and `--no-cache` out of the argument list.
+/// Caching is on by default, under `DEFAULT_CACHE_DIR`.
+fn extract_cache_config(args: &mut Vec<String>) -> CacheConfig {
+    let mut dir = PathBuf::from(DEFAULT_CACHE_DIR);
+    let mut enabled = true;
+
+    if let Some(pos) = args.iter().position(|a| a == "--no-cache") {
+        args.remove(pos);
+        enabled = false;
+    }
+
+    if let Some(pos) = args.iter().position(|a| a == "--cache-dir") {
+        args.remove(pos);
+        if pos < args.len() {
+            dir = PathBuf::from(args.remove(pos));
+        }
+    }
+
+    CacheConfig { dir, enabled }
+}
+
+/// Hashes file contents with a stable 64-bit hash (FNV-1a, whose output
+/// does not change between Rust releases) for cache keying.
+fn hash_contents(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Builds the `<path>:<hash>` cache key, sanitized into a flat filename.
+fn cache_key(path: &Path, hash: u64) -> String {
+    let sanitized: String = path
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '.' { c } else { '_' })
+        .collect();
+    format!("{}__{:016x}.json", sanitized, hash)
+}
+
+fn read_cache(cache_dir: &Path, path: &Path, hash: u64) -> Option<ParseResult> {
+    let cache_path = cache_dir.join(cache_key(path, hash));
+    let data = fs::read_to_string(cache_path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn write_cache(cache_dir: &Path, path: &Path, hash: u64, result: &ParseResult) {
+    if fs::create_dir_all(cache_dir).is_err() {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(result) {
+        let _ = fs::write(cache_dir.join(cache_key(path, hash)), json);
+    }
+}
+
+/// Parses `content` with `RustVisitor`, exiting via `emit_error` in the
+/// requested `format` on a syntax error.
+fn parse_rust_source(content: &str, path: &Path, format: OutputFormat) -> ParseResult {
+    match syn::parse_file(content) {
+        Ok(tree) => {
+            let mut visitor = RustVisitor::new();
+            visitor.visit_file(&tree);
+            visitor.result
+        }
+        Err(e) => {
+            emit_error(&format!("Parse error in {}: {}", path.display(), e), format);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses `path`'s contents, consulting (and populating) the file-hash
+/// keyed cache in `cache.dir` unless caching is disabled, so re-running
+/// over an unchanged file is close to free.
+fn parse_with_cache(
+    path: &Path,
+    content: &str,
+    format: OutputFormat,
+    cache: &CacheConfig,
+) -> ParseResult {
+    let hash = hash_contents(content);
+
+    if cache.enabled {
+        if let Some(cached) = read_cache(&cache.dir, path, hash) {
+            return cached;
+        }
+    }
+
+    let result = parse_rust_source(content, path, format);
+
+    if cache.enabled {
+        write_cache(&cache.dir, path, hash, &result);
+    }
+
+    result
+}
+
+/// Walks every `.rs` file under `dir` (its `src/` subdirectory if present),
+/// parses each independently, then resolves calls against a crate-wide
+/// symbol table so `DependencyInfo.module` and the returned `call_graph`
+/// reflect cross-module structure rather than a single file in isolation.
+fn run_crate_mode(dir: &str, format: OutputFormat, cache: &CacheConfig) {
+    let root = Path::new(dir);
+    let src_root = if root.join("src").is_dir() {
+        root.join("src")
+    } else {
+        root.to_path_buf()
+    };
+
+    let mut paths = Vec::new();
+    if let Err(e) = collect_rust_files(&src_root, &mut paths) {
+        emit_error(&format!("Failed to walk crate directory: {}", e), format);
+        std::process::exit(1);
+    }
+
+    let mut file_results = Vec::new();
+
+    for path in &paths {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                emit_error(&format!("Failed to read file: {}", e), format);
+                std::process::exit(1);
             }
-            syn::Expr::Call(call) => {
-                // Extract function calls
-                let func_name = quote::quote!(#call).to_string();
-
-                // Detect side effects
-                if func_name.contains("println!")
-                    || func_name.contains("print!")
-                    || func_name.contains("write!")
-                    || func_name.contains("File::") {
-                    if !self.result.side_effects.contains(&"io_operation".to_string()) {
-                        self.result.side_effects.push("io_operation".to_string());
-                    }
-                }
+        };
+
+        let result = parse_with_cache(path, &content, format, cache);
+        let module = module_path_for(path, &src_root);
+
+        file_results.push(FileResult {
+            path: path.display().to_string(),
+            module,
+            result,
+        });
+    }
+
+    // Crate-wide symbol tables, built only after every file has been
+    // parsed so resolution can consider definitions regardless of which
+    // file happens to be processed first.
+    let mut qualified_symbols: HashMap<String, String> = HashMap::new();
+    let mut name_modules: HashMap<String, HashSet<String>> = HashMap::new();
+    for file_result in &file_results {
+        for func in &file_result.result.functions {
+            let qualified = qualify_name(&file_result.module, &func.name);
+            qualified_symbols.insert(qualified, file_result.module.clone());
+            name_modules
+                .entry(func.name.clone())
+                .or_default()
+                .insert(file_result.module.clone());
+        }
+    }
+
+    let mut call_graph = Vec::new();
+    for file_result in &mut file_results {
+        let import_paths: Vec<String> = file_result
+            .result
+            .imports
+            .iter()
+            .flat_map(|import| parse_import_paths(import))
+            .collect();
+
+        for dep in &mut file_result.result.dependencies {
+            let callee = extract_callee_path(&dep.function);
+            dep.module = resolve_callee_module(
+                &callee,
+                &file_result.module,
+                &import_paths,
+                &qualified_symbols,
+                &name_modules,
+            );
 
-                self.result.dependencies.push(DependencyInfo {
-                    function: func_name,
-                    module: None,
+            if let Some(caller) = &dep.caller {
+                call_graph.push(CallGraphEdge {
+                    caller: qualify_name(&file_result.module, caller),
+                    callee: callee.clone(),
+                    module: dep.module.clone(),
                 });
             }
-            _ => {}
         }
+    }
 
-        visit::visit_expr(self, expr);
+    let crate_result = CrateResult { files: file_results, call_graph };
+
+    match emit(&crate_result, format) {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            emit_error(&e, format);
+            std::process::exit(1);
+        }
     }
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let format = extract_format(&mut args);
+    let cache = extract_cache_config(&mut args);
+
+    if args.len() >= 3 && args[1] == "--crate" {
+        run_crate_mode(&args[2], format, &cache);
+        return;
+    }
 
     if args.len() < 2 {
-        eprintln!(r#"{{"error": "No file path provided"}}"#);
+        emit_error("No file path provided", format);
         std::process::exit(1);
     }
 
     let file_path = &args[1];
 
     match fs::read_to_string(file_path) {
-        Ok(content) => match syn::parse_file(&content) {
-            Ok(syntax_tree) => {
-                let mut visitor = RustVisitor::new();
-                visitor.visit_file(&syntax_tree);
-
-                match serde_json::to_string(&visitor.result) {
-                    Ok(json) => {
-                        println!("{}", json);
-                        std::process::exit(0);
-                    }
-                    Err(e) => {
-                        eprintln!(r#"{{"error": "JSON encoding failed: {}"}}"#, e);
-                        std::process::exit(1);
-                    }
+        Ok(content) => {
+            let result = parse_with_cache(Path::new(file_path), &content, format, &cache);
+
+            match emit(&result, format) {
+                Ok(()) => std::process::exit(0),
+                Err(e) => {
+                    emit_error(&e, format);
+                    std::process::exit(1);
                 }
             }
-            Err(e) => {
-                eprintln!(r#"{{"error": "Parse error: {}"}}"#, e);
-                std::process::exit(1);
-            }
-        },
+        }
         Err(e) => {
-            eprintln!(r#"{{"error": "Failed to read file: {}"}}"#, e);
+            emit_error(&format!("Failed to read file: {}", e), format);
             std::process::exit(1);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each item in a file should be recorded exactly once; a visitor
+    /// that both manually dispatches and delegates to syn's default
+    /// traversal would double every function/struct/trait/impl.
+    #[test]
+    fn visit_item_does_not_double_count_items() {
+        let source = r#"
+            fn standalone(x: i32) -> i32 { x + 1 }
+
+            struct Widget { id: u32 }
+
+            trait Greets { fn greet(&self); }
+
+            impl Widget {
+                fn new() -> Self { Widget { id: 0 } }
+            }
+        "#;
+        let result = parse_rust_source(source, Path::new("lib.rs"), OutputFormat::Json);
+        assert_eq!(result.functions.len(), 1);
+        assert_eq!(result.structs.len(), 1);
+        assert_eq!(result.traits.len(), 1);
+        assert_eq!(result.impls.len(), 1);
+    }
+
+    /// Nested branches weight cognitive complexity by their nesting
+    /// depth (`1 + nesting` per branch) while cyclomatic complexity
+    /// stays a flat `+1` per branch.
+    #[test]
+    fn cognitive_complexity_weights_nesting_depth() {
+        let source = r#"
+            fn outer(x: i32) -> i32 {
+                if x > 0 {
+                    if x > 10 { 1 } else { 2 }
+                } else {
+                    0
+                }
+            }
+        "#;
+        let result = parse_rust_source(source, Path::new("lib.rs"), OutputFormat::Json);
+        assert_eq!(result.functions[0].cyclomatic, 3);
+        assert_eq!(result.functions[0].cognitive, 3);
+    }
+
+    /// A crate-root definition (empty module) must resolve through the
+    /// same qualified key the symbol table was built with, even when a
+    /// same-named function also exists in another module.
+    #[test]
+    fn resolve_callee_module_finds_crate_root_definition() {
+        let mut qualified_symbols = HashMap::new();
+        qualified_symbols.insert(qualify_name("", "helper"), String::new());
+        qualified_symbols.insert(qualify_name("util", "helper"), "util".to_string());
+
+        let mut name_modules: HashMap<String, HashSet<String>> = HashMap::new();
+        name_modules
+            .entry("helper".to_string())
+            .or_default()
+            .extend([String::new(), "util".to_string()]);
+
+        let resolved =
+            resolve_callee_module("helper", "", &[], &qualified_symbols, &name_modules);
+        assert_eq!(resolved, Some(String::new()));
+    }
+
+    /// A method call resolves its effect through `EFFECT_METHOD_TABLE`
+    /// (by method name) rather than `EFFECT_PATH_TABLE`, which only ever
+    /// matches fully-qualified free-function calls.
+    #[test]
+    fn method_calls_classify_effects_by_method_name() {
+        let source = r#"
+            fn load(f: &mut std::fs::File) {
+                let mut buf = String::new();
+                f.read_to_string(&mut buf).unwrap();
+            }
+        "#;
+        let result = parse_rust_source(source, Path::new("lib.rs"), OutputFormat::Json);
+        assert_eq!(result.functions[0].effects, vec![Effect::Io, Effect::Panic]);
+    }
+
+    /// Assigning through a bare path (e.g. a `static`) is a mutation too,
+    /// not just assignment through a raw/`&mut` deref.
+    #[test]
+    fn path_assignment_is_a_mutation() {
+        let source = r#"
+            fn bump() {
+                unsafe { COUNTER = 5; }
+            }
+        "#;
+        let result = parse_rust_source(source, Path::new("lib.rs"), OutputFormat::Json);
+        assert!(result.functions[0].effects.contains(&Effect::Mutation));
+        assert!(result.functions[0].effects.contains(&Effect::Unsafe));
+    }
+
+    /// A cache hit returns the previously cached result instead of
+    /// re-parsing `content`, even when that cached result has since
+    /// diverged from what a fresh parse would produce.
+    #[test]
+    fn parse_with_cache_returns_cached_result_on_hit() {
+        let dir = std::env::temp_dir().join("rust_parser_cache_test_hit");
+        let _ = fs::remove_dir_all(&dir);
+        let cache = CacheConfig {
+            dir: dir.clone(),
+            enabled: true,
+        };
+        let path = Path::new("cached.rs");
+        let content = "fn real() {}";
+
+        let mut stale = parse_rust_source(content, path, OutputFormat::Json);
+        stale.functions[0].name = "stale_cached_name".to_string();
+        write_cache(&cache.dir, path, hash_contents(content), &stale);
+
+        let result = parse_with_cache(path, content, OutputFormat::Json, &cache);
+        assert_eq!(result.functions[0].name, "stale_cached_name");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// With caching disabled, `parse_with_cache` never reads or writes
+    /// the cache directory.
+    #[test]
+    fn parse_with_cache_skips_cache_when_disabled() {
+        let dir = std::env::temp_dir().join("rust_parser_cache_test_disabled");
+        let _ = fs::remove_dir_all(&dir);
+        let cache = CacheConfig {
+            dir: dir.clone(),
+            enabled: false,
+        };
+        let path = Path::new("uncached.rs");
+        let content = "fn real() {}";
+
+        let result = parse_with_cache(path, content, OutputFormat::Json, &cache);
+        assert_eq!(result.functions[0].name, "real");
+        assert!(!dir.exists());
+    }
+
+    /// `resolve_callee_module` tries, in order: an exact qualified match,
+    /// an import-aliased qualified match, the caller's own module, and
+    /// finally a crate-wide unambiguous bare name — leaving `None` when
+    /// two modules define the same bare name and nothing else narrows it.
+    #[test]
+    fn resolve_callee_module_tries_each_fallback_in_order() {
+        let mut qualified_symbols = HashMap::new();
+        qualified_symbols.insert("a::helper".to_string(), "a".to_string());
+        qualified_symbols.insert("b::helper".to_string(), "b".to_string());
+        qualified_symbols.insert("c::local_only".to_string(), "c".to_string());
+        qualified_symbols.insert("x::d::imported".to_string(), "x::d".to_string());
+
+        let mut name_modules: HashMap<String, HashSet<String>> = HashMap::new();
+        name_modules
+            .entry("helper".to_string())
+            .or_default()
+            .extend(["a".to_string(), "b".to_string()]);
+        name_modules
+            .entry("unique_fn".to_string())
+            .or_default()
+            .insert("e".to_string());
+        qualified_symbols.insert("e::unique_fn".to_string(), "e".to_string());
+
+        // Exact qualified match wins immediately.
+        assert_eq!(
+            resolve_callee_module("a::helper", "c", &[], &qualified_symbols, &name_modules),
+            Some("a".to_string())
+        );
+
+        // Qualified match resolved through an import whose last segment
+        // matches the call's leading qualifier (`use x::d;` then `d::imported(...)`).
+        assert_eq!(
+            resolve_callee_module(
+                "d::imported",
+                "c",
+                &["x::d".to_string()],
+                &qualified_symbols,
+                &name_modules
+            ),
+            Some("x::d".to_string())
+        );
+
+        // Unqualified call defined in the caller's own module.
+        assert_eq!(
+            resolve_callee_module("local_only", "c", &[], &qualified_symbols, &name_modules),
+            Some("c".to_string())
+        );
+
+        // Unqualified call with no local/import match, unambiguous crate-wide.
+        assert_eq!(
+            resolve_callee_module("unique_fn", "c", &[], &qualified_symbols, &name_modules),
+            Some("e".to_string())
+        );
+
+        // Ambiguous bare name defined in two modules: left unresolved.
+        assert_eq!(
+            resolve_callee_module("helper", "c", &[], &qualified_symbols, &name_modules),
+            None
+        );
+    }
+
+    /// A `ParseResult` with a mix of present and omitted `Option` fields,
+    /// so round-trip tests exercise `skip_serializing_if`-driven field
+    /// omission rather than only the all-fields-present case.
+    fn sample_parse_result() -> ParseResult {
+        ParseResult {
+            functions: vec![
+                FunctionInfo {
+                    name: "helper".to_string(),
+                    arity: 1,
+                    params: vec!["x: i32".to_string()],
+                    public: true,
+                    async_fn: Some(false),
+                    cyclomatic: 2,
+                    cognitive: 1,
+                    effects: vec![Effect::Io, Effect::Panic],
+                    doc: Some("Does a thing.".to_string()),
+                    attributes: vec!["#[inline]".to_string()],
+                    generics: vec![],
+                    return_type: Some("i32".to_string()),
+                },
+                FunctionInfo {
+                    name: "bare".to_string(),
+                    arity: 0,
+                    params: vec![],
+                    public: false,
+                    async_fn: None,
+                    cyclomatic: 1,
+                    cognitive: 1,
+                    effects: vec![],
+                    doc: None,
+                    attributes: vec![],
+                    generics: vec![],
+                    return_type: None,
+                },
+            ],
+            structs: vec![TypeInfo {
+                name: "Widget".to_string(),
+                kind: "struct".to_string(),
+                public: true,
+                fields: Some(vec!["id: u32".to_string()]),
+                methods: None,
+                doc: None,
+                attributes: vec![],
+                generics: vec![],
+            }],
+            traits: vec![],
+            impls: vec![],
+            imports: vec!["use std :: fmt ;".to_string()],
+            dependencies: vec![DependencyInfo {
+                function: "helper()".to_string(),
+                module: Some("widgets".to_string()),
+                caller: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let original = sample_parse_result();
+        let encoded = serde_json::to_string(&original).unwrap();
+        let decoded: ParseResult = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded.functions.len(), original.functions.len());
+        assert_eq!(decoded.functions[1].doc, None);
+    }
+
+    #[test]
+    fn ron_round_trips() {
+        let original = sample_parse_result();
+        let encoded = ron::to_string(&original).unwrap();
+        let decoded: ParseResult = ron::from_str(&encoded).unwrap();
+        assert_eq!(decoded.functions.len(), original.functions.len());
+        assert_eq!(decoded.structs[0].fields, original.structs[0].fields);
+    }
+
+    #[test]
+    fn toml_round_trips() {
+        let original = sample_parse_result();
+        let encoded = toml::to_string(&original).unwrap();
+        let decoded: ParseResult = toml::from_str(&encoded).unwrap();
+        assert_eq!(decoded.functions.len(), original.functions.len());
+        assert_eq!(decoded.dependencies[0].module, original.dependencies[0].module);
+    }
+
+    #[test]
+    fn msgpack_round_trips() {
+        let original = sample_parse_result();
+        let mut encoded = Vec::new();
+        original
+            .serialize(&mut rmp_serde::Serializer::new(&mut encoded).with_struct_map())
+            .unwrap();
+        let decoded: ParseResult = rmp_serde::from_slice(&encoded).unwrap();
+        assert_eq!(decoded.functions.len(), original.functions.len());
+        assert_eq!(decoded.functions[0].async_fn, original.functions[0].async_fn);
+        assert_eq!(decoded.functions[1].async_fn, None);
+    }
+}